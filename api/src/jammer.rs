@@ -1,8 +1,10 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
 use nockapp_grpc::services::private_nockapp::client::PrivateNockAppGrpcClient;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use tonic::transport::Channel;
 
@@ -22,6 +24,32 @@ pub struct JammerConfig {
     pub nockchain_dir: PathBuf,
     pub nockchain_user: Option<String>,
     pub nockchain_service: String,
+    pub manifest_gate: ManifestGate,
+}
+
+/// Shutdown gate for manifest writes, held across the temp-then-rename write.
+/// Once closed, no further write (and so no new `.tmp`) can happen.
+#[derive(Clone)]
+pub struct ManifestGate(Arc<std::sync::Mutex<bool>>);
+
+impl ManifestGate {
+    pub fn open() -> Self {
+        Self(Arc::new(std::sync::Mutex::new(true)))
+    }
+
+    /// Blocks until any write in progress has finished.
+    fn close(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = false;
+    }
+}
+
+/// One line of `SHA256SUMS`, with the current on-disk size of the file.
+/// `size_bytes` is `None` when the listed file no longer exists.
+#[derive(Serialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub sha256: String,
+    pub size_bytes: Option<u64>,
 }
 
 pub async fn get_tip_block(config: &JammerConfig) -> Result<u64> {
@@ -103,17 +131,18 @@ pub async fn export_state_to_jam(
 
 /// Runs the entire export → manifest flow.
 /// Uses live `NockApp::export_state` on the running node (private gRPC).
-/// `set_phase` is called as work progresses (for `/api/status`).
+/// `set_phase` is called as work progresses (for `/api/status`), with the
+/// target jam path once the tip is known (`None` leaves it unchanged).
 pub async fn run_jam<F, Fut>(
     config: &JammerConfig,
     log: &JobLog,
     mut set_phase: F,
 ) -> Result<String>
 where
-    F: FnMut(String) -> Fut,
+    F: FnMut(String, Option<String>) -> Fut,
     Fut: std::future::Future<Output = ()>,
 {
-    set_phase("fetching_tip".into()).await;
+    set_phase("fetching_tip".into(), None).await;
     let tip = get_tip_block(config)
         .await
         .context("Failed to get tip block")?;
@@ -130,23 +159,28 @@ where
             "[jammer] Jam already exists: {} (skipping)",
             jam_path.display()
         ));
-        set_phase("manifest".into()).await;
+        set_phase("manifest".into(), Some(jam_path.display().to_string())).await;
         write_manifest(config, log).await?;
         return Ok(format!("Jam for block {} already exists", tip));
     }
 
     std::fs::create_dir_all(&config.jams_dir).context("Failed to create jams directory")?;
+    // Export under a name the manifest ignores, so an interrupted export is
+    // never hashed or mistaken for a finished jam.
+    let partial_path = partial_jam_path(&jam_path);
     log.append(&format!(
         "[jammer] Exporting live state to: {}",
-        jam_path.display()
+        partial_path.display()
     ));
 
-    set_phase("exporting".into()).await;
-    export_state_to_jam(&config.nockchain_private_grpc, &jam_path, log)
+    set_phase("exporting".into(), Some(jam_path.display().to_string())).await;
+    export_state_to_jam(&config.nockchain_private_grpc, &partial_path, log)
         .await
         .context("Live state export failed")?;
+    std::fs::rename(&partial_path, &jam_path)
+        .with_context(|| format!("Failed to rename {}", partial_path.display()))?;
 
-    set_phase("manifest".into()).await;
+    set_phase("manifest".into(), None).await;
     write_manifest(config, log).await?;
 
     Ok(format!("Exported jam for block {}", tip))
@@ -192,10 +226,58 @@ fn collect_hashable_files(html_root: &Path, jams_dir: &Path) -> Vec<PathBuf> {
     files
 }
 
+fn manifest_tmp_path(manifest_path: &Path) -> PathBuf {
+    manifest_path.with_extension("tmp")
+}
+
+fn partial_jam_path(jam_path: &Path) -> PathBuf {
+    jam_path.with_extension("jam.partial")
+}
+
+fn remove_stale_file(path: &Path) {
+    match std::fs::remove_file(path) {
+        Ok(()) => eprintln!("[jammer] Removed stale file: {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => eprintln!("[jammer] Failed to remove {}: {}", path.display(), e),
+    }
+}
+
+/// Removes leftovers from an interrupted run: the temp manifest and any
+/// partial jam exports.
+pub fn remove_stale_files(manifest_path: &Path, jams_dir: &Path) {
+    remove_stale_file(&manifest_tmp_path(manifest_path));
+
+    if let Ok(entries) = std::fs::read_dir(jams_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "partial") {
+                remove_stale_file(&path);
+            }
+        }
+    }
+}
+
+/// Closes the manifest gate (waiting out a write in progress), then removes
+/// stale files so nothing half-written outlives the process.
+pub async fn close(config: &JammerConfig) {
+    let gate = config.manifest_gate.clone();
+    let manifest_path = config.manifest_path.clone();
+    let jams_dir = config.jams_dir.clone();
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        gate.close();
+        remove_stale_files(&manifest_path, &jams_dir);
+        let _ = tx.send(());
+    });
+    let _ = rx.await;
+}
+
 fn write_manifest_sync(
     html_root: &Path,
     jams_dir: &Path,
     manifest_path: &Path,
+    gate: &ManifestGate,
     log: &JobLog,
 ) -> Result<()> {
     log.append(&format!(
@@ -234,9 +316,14 @@ fn write_manifest_sync(
         content.push_str(&format!("{}  {}\n", hash, rel));
     }
 
-    let tmp = manifest_path.with_extension("tmp");
+    let open = gate.0.lock().unwrap_or_else(|e| e.into_inner());
+    if !*open {
+        bail!("Shutting down; manifest not written");
+    }
+    let tmp = manifest_tmp_path(manifest_path);
     std::fs::write(&tmp, &content).context("Failed to write temp manifest")?;
     std::fs::rename(&tmp, manifest_path).context("Failed to rename manifest")?;
+    drop(open);
 
     #[cfg(unix)]
     {
@@ -256,14 +343,127 @@ pub async fn write_manifest(config: &JammerConfig, log: &JobLog) -> Result<()> {
     let html_root = config.html_root.clone();
     let jams_dir = config.jams_dir.clone();
     let manifest_path = config.manifest_path.clone();
+    let gate = config.manifest_gate.clone();
     let log = log.clone();
 
     let (tx, rx) = tokio::sync::oneshot::channel::<Result<()>>();
     std::thread::spawn(move || {
-        let result = write_manifest_sync(&html_root, &jams_dir, &manifest_path, &log);
+        let result = write_manifest_sync(&html_root, &jams_dir, &manifest_path, &gate, &log);
         let _ = tx.send(result);
     });
     rx.await
         .context("manifest thread dropped sender")?
         .context("Manifest task failed")
 }
+
+/// Parses `"<hash>  <relpath>"` lines from the manifest and stats each file
+/// under `html_root`. Returns `Ok(None)` if the manifest has not been written yet
+/// and an error if any line is malformed.
+pub fn read_manifest(html_root: &Path, manifest_path: &Path) -> Result<Option<Vec<ManifestEntry>>> {
+    let content = match std::fs::read_to_string(manifest_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", manifest_path.display()))
+        }
+    };
+
+    let mut entries = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((hash, rel)) = line.split_once("  ") else {
+            bail!("Malformed manifest line {}: {:?}", n + 1, line);
+        };
+        let size_bytes = std::fs::metadata(html_root.join(rel))
+            .ok()
+            .filter(|m| m.is_file())
+            .map(|m| m.len());
+        entries.push(ManifestEntry {
+            path: rel.to_string(),
+            sha256: hash.to_string(),
+            size_bytes,
+        });
+    }
+    Ok(Some(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("nockchain-jammer-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("jams")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn read_manifest_missing() {
+        let root = temp_root("missing");
+        let manifest = root.join("jams/SHA256SUMS");
+        assert!(read_manifest(&root, &manifest).unwrap().is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn read_manifest_entries() {
+        let root = temp_root("entries");
+        let manifest = root.join("jams/SHA256SUMS");
+        std::fs::write(root.join("jams/100.jam"), b"jam!").unwrap();
+        std::fs::write(&manifest, "aaaa  jams/100.jam\nbbbb  jams/99.jam\n").unwrap();
+
+        let entries = read_manifest(&root, &manifest).unwrap().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, "jams/100.jam");
+        assert_eq!(entries[0].sha256, "aaaa");
+        assert_eq!(entries[0].size_bytes, Some(4));
+        assert_eq!(entries[1].path, "jams/99.jam");
+        assert_eq!(entries[1].size_bytes, None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn write_manifest_gate_closed() {
+        let root = temp_root("gate");
+        let manifest = root.join("jams/SHA256SUMS");
+        std::fs::write(root.join("jams/100.jam"), b"jam!").unwrap();
+        std::fs::write(&manifest, "old\n").unwrap();
+
+        let gate = ManifestGate::open();
+        gate.close();
+        let result =
+            write_manifest_sync(&root, &root.join("jams"), &manifest, &gate, &JobLog::new());
+        assert!(result.is_err());
+        assert!(!manifest_tmp_path(&manifest).exists());
+        assert_eq!(std::fs::read_to_string(&manifest).unwrap(), "old\n");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn remove_stale_files_cleans_up() {
+        let root = temp_root("stale");
+        let manifest = root.join("jams/SHA256SUMS");
+        std::fs::write(manifest_tmp_path(&manifest), "partial").unwrap();
+        std::fs::write(root.join("jams/100.jam.partial"), b"ja").unwrap();
+        std::fs::write(root.join("jams/99.jam"), b"jam!").unwrap();
+
+        remove_stale_files(&manifest, &root.join("jams"));
+        assert!(!manifest_tmp_path(&manifest).exists());
+        assert!(!root.join("jams/100.jam.partial").exists());
+        assert!(root.join("jams/99.jam").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn read_manifest_malformed() {
+        let root = temp_root("malformed");
+        let manifest = root.join("jams/SHA256SUMS");
+        std::fs::write(&manifest, "aaaa  jams/100.jam\ngarbage\n").unwrap();
+        assert!(read_manifest(&root, &manifest).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
+use std::future::IntoFuture;
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use axum::extract::State;
 use axum::http::{HeaderMap, StatusCode};
@@ -21,11 +22,13 @@ mod proto {
 struct JobState {
     running: bool,
     phase: Option<String>,
+    target: Option<String>,
     started_at: Option<Instant>,
     last_completed: Option<String>,
     last_success: Option<bool>,
     last_output: Option<String>,
     live_log: Option<JobLog>,
+    shutting_down: bool,
 }
 
 /// Thread-safe log buffer that jammer writes to during a job.
@@ -105,6 +108,16 @@ async fn make_jam(State(state): State<Arc<AppState>>, headers: HeaderMap) -> imp
     }
 
     let mut job = state.job.lock().await;
+    if job.shutting_down {
+        eprintln!("[make-jam] rejected: shutting down");
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(JobResult {
+                success: false,
+                output: "server is shutting down".into(),
+            }),
+        );
+    }
     if job.running {
         eprintln!("[make-jam] rejected: job already running");
         return (
@@ -118,6 +131,7 @@ async fn make_jam(State(state): State<Arc<AppState>>, headers: HeaderMap) -> imp
     let log = JobLog::new();
     job.running = true;
     job.phase = Some("starting".into());
+    job.target = None;
     job.started_at = Some(Instant::now());
     job.live_log = Some(log.clone());
     drop(job);
@@ -128,11 +142,14 @@ async fn make_jam(State(state): State<Arc<AppState>>, headers: HeaderMap) -> imp
     let bg_log = log.clone();
     tokio::spawn(async move {
         let start = Instant::now();
-        let result = jammer::run_jam(&bg_state.config, &bg_log, |phase| {
+        let result = jammer::run_jam(&bg_state.config, &bg_log, |phase, target| {
             let state = Arc::clone(&bg_state);
             async move {
                 let mut job = state.job.lock().await;
                 job.phase = Some(phase);
+                if target.is_some() {
+                    job.target = target;
+                }
             }
         })
         .await;
@@ -156,6 +173,7 @@ async fn make_jam(State(state): State<Arc<AppState>>, headers: HeaderMap) -> imp
         let mut job = bg_state.job.lock().await;
         job.running = false;
         job.phase = None;
+        job.target = None;
         job.started_at = None;
         job.last_completed = Some(finished_at);
         job.last_success = Some(result.is_ok());
@@ -183,6 +201,46 @@ fn count_jams(dir: PathBuf) -> usize {
         .unwrap_or(0)
 }
 
+async fn manifest(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let html_root = state.config.html_root.clone();
+    let manifest_path = state.config.manifest_path.clone();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(jammer::read_manifest(&html_root, &manifest_path));
+    });
+
+    match rx.await {
+        Ok(Ok(Some(entries))) => (StatusCode::OK, Json(entries)).into_response(),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(JobResult {
+                success: false,
+                output: "manifest not generated yet".into(),
+            }),
+        )
+            .into_response(),
+        Ok(Err(e)) => {
+            eprintln!("[manifest] failed: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(JobResult {
+                    success: false,
+                    output: format!("failed to read manifest: {:#}", e),
+                }),
+            )
+                .into_response()
+        }
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(JobResult {
+                success: false,
+                output: "manifest reader thread dropped".into(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
 async fn status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let job = state.job.lock().await;
     let running_for_secs = job.started_at.map(|t| t.elapsed().as_secs());
@@ -216,6 +274,41 @@ async fn status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     })
 }
 
+/// Resolves on SIGTERM or SIGINT.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => eprintln!("[shutdown] received SIGINT"),
+        _ = terminate => eprintln!("[shutdown] received SIGTERM"),
+    }
+}
+
+/// Waits for an in-flight job to finish, giving up at `deadline`.
+async fn wait_for_job(state: &AppState, deadline: Instant) {
+    while Instant::now() < deadline {
+        if !state.job.lock().await.running {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+}
+
 fn env_or(key: &str, default: &str) -> String {
     std::env::var(key).unwrap_or_else(|_| default.into())
 }
@@ -243,6 +336,7 @@ async fn main() {
             .ok()
             .filter(|s| !s.is_empty()),
         nockchain_service: env_or("NOCKCHAIN_SERVICE", "nockchain"),
+        manifest_gate: jammer::ManifestGate::open(),
     };
 
     eprintln!("config: JAMS_DIR={}", config.jams_dir.display());
@@ -260,17 +354,31 @@ async fn main() {
     );
     eprintln!("config: NOCKCHAIN_SERVICE={}", config.nockchain_service);
 
+    let shutdown_grace = Duration::from_secs(
+        env_or("SHUTDOWN_GRACE_SECS", "30")
+            .parse()
+            .unwrap_or_else(|_| {
+                eprintln!("WARNING: invalid SHUTDOWN_GRACE_SECS, using 30");
+                30
+            }),
+    );
+    eprintln!("config: SHUTDOWN_GRACE_SECS={}", shutdown_grace.as_secs());
+
+    jammer::remove_stale_files(&config.manifest_path, &config.jams_dir);
+
     let state = Arc::new(AppState {
         api_key,
         config,
         job: Mutex::new(JobState {
             running: false,
             phase: None,
+            target: None,
             started_at: None,
             last_completed: None,
             last_success: None,
             last_output: None,
             live_log: None,
+            shutting_down: false,
         }),
     });
 
@@ -284,14 +392,61 @@ async fn main() {
     let app = Router::new()
         .route("/api/make-jam", post(make_jam))
         .route("/api/status", get(status))
+        .route("/api/manifest", get(manifest))
         .route("/", get(|| async { Redirect::permanent("/jams/") }))
         .nest_service("/jams", jams_service)
         .layer(cors)
-        .with_state(state);
+        .with_state(Arc::clone(&state));
 
     let port = env_or("API_PORT", "80");
     let addr = format!("0.0.0.0:{}", port);
     eprintln!("listening on {addr}");
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let shutdown = Arc::new(tokio::sync::Notify::new());
+    let server = {
+        let shutdown = Arc::clone(&shutdown);
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async move { shutdown.notified().await })
+            .into_future()
+    };
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => {
+            result.unwrap();
+            return;
+        }
+        _ = shutdown_signal() => {}
+    }
+
+    // The grace period covers both the connection drain and the running job.
+    let deadline = Instant::now() + shutdown_grace;
+    state.job.lock().await.shutting_down = true;
+    shutdown.notify_one();
+
+    tokio::select! {
+        _ = async {
+            match tokio::time::timeout_at(deadline.into(), &mut server).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => eprintln!("[shutdown] server error: {}", e),
+                Err(_) => eprintln!("[shutdown] grace period elapsed; dropping open connections"),
+            }
+            wait_for_job(&state, deadline).await;
+        } => {}
+        _ = shutdown_signal() => eprintln!("[shutdown] second signal; exiting now"),
+    }
+
+    let job = state.job.lock().await;
+    if job.running {
+        eprintln!(
+            "[shutdown] abandoning job for {} in phase {} (running for {}s); it may be incomplete",
+            job.target.as_deref().unwrap_or("unknown tip"),
+            job.phase.as_deref().unwrap_or("unknown"),
+            job.started_at.map(|t| t.elapsed().as_secs()).unwrap_or(0)
+        );
+    }
+    drop(job);
+
+    jammer::close(&state.config).await;
+    eprintln!("[shutdown] exiting");
 }